@@ -1,13 +1,11 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
 use std::fs::{read_to_string, File};
 use std::io::{self, prelude::*};
 use std::path::Path;
-use std::{
-    env,
-    time::{Duration, SystemTime},
-};
+use std::{env, time::Instant, time::SystemTime};
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 enum Status {
     Todo,
     InProgress,
@@ -24,6 +22,321 @@ impl std::fmt::Display for Status {
     }
 }
 
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+enum Priority {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+impl std::fmt::Display for Priority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let display = match &self {
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+        };
+        f.write_str(display)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+enum OrderKey {
+    #[default]
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    Due,
+    Priority,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+// A composable filter/order for `list`, built from `key:value` clauses on the command line
+#[derive(Clone, Debug, Default)]
+struct ListQuery {
+    status: HashSet<Status>,
+    priority: Option<Priority>,
+    tag: Option<String>,
+    order: OrderKey,
+    direction: SortDirection,
+    actionable_only: bool,
+}
+
+// Logged time is kept as hours/minutes rather than a single minute count so the
+// file format and any printed summary stay readable (e.g. "1h30m").
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Duration {
+    hours: u16,
+    minutes: u16,
+}
+
+impl Duration {
+    fn new(hours: u16, minutes: u16) -> Result<Self, String> {
+        if minutes >= 60 {
+            return Err(format!(
+                "minutes must be less than 60, got {}",
+                minutes
+            ));
+        }
+        Ok(Duration { hours, minutes })
+    }
+}
+
+impl std::fmt::Display for Duration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.minutes >= 60 {
+            // Refuse to print (and therefore persist) an invalid duration.
+            return Err(std::fmt::Error);
+        }
+        write!(f, "{}h{}m", self.hours, self.minutes)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TimeEntry {
+    logged_at: SystemTime,
+    duration: Duration,
+    message: Option<String>,
+}
+
+impl TimeEntry {
+    fn new(
+        logged_at: SystemTime,
+        duration: Duration,
+        message: Option<String>,
+    ) -> Result<Self, String> {
+        if duration.minutes >= 60 {
+            return Err(format!(
+                "minutes must be less than 60, got {}",
+                duration.minutes
+            ));
+        }
+        Ok(TimeEntry {
+            logged_at,
+            duration,
+            message,
+        })
+    }
+}
+
+impl std::fmt::Display for TimeEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.duration.minutes >= 60 {
+            return Err(std::fmt::Error);
+        }
+        let logged_at_secs = self
+            .logged_at
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let message = escape_json_string(self.message.as_deref().unwrap_or(""));
+        write!(f, "{}~{}~{}", logged_at_secs, self.duration, message)
+    }
+}
+
+// A single execution of a task's attached command, kept in `Task::run_history`
+#[derive(Debug, Clone)]
+struct RunResult {
+    run_started: SystemTime,
+    duration: std::time::Duration,
+    stdout: String,
+    stderr: String,
+    return_code: i32,
+}
+
+impl std::fmt::Display for RunResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let run_started_secs = self
+            .run_started
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let stdout = escape_json_string(&self.stdout);
+        let stderr = escape_json_string(&self.stderr);
+        write!(
+            f,
+            "{}~{}~{}~{}~{}",
+            run_started_secs,
+            self.duration.as_secs(),
+            self.return_code,
+            stdout,
+            stderr
+        )
+    }
+}
+
+// Escapes a string for embedding between the double quotes of a task.json field, so a
+// description (or any other free-text field) round-trips losslessly through `tokenize`
+fn escape_json_string(raw: &str) -> String {
+    let mut escaped = String::with_capacity(raw.len());
+    for c in raw.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// A single lexical token produced while scanning the raw contents of tasks.json
+#[derive(Debug, Clone, PartialEq)]
+enum JsonToken {
+    LBrace,
+    RBrace,
+    Colon,
+    Comma,
+    Str(String),
+    Literal(String),
+}
+
+// Scans tasks.json character by character, tracking quoted-string state and honoring `\"`/`\\`/`\n`
+// escapes, rather than the old approach of stripping every `"` and splitting on `,`/`:`
+fn tokenize(data: &str) -> Result<Vec<JsonToken>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = data.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            '{' => {
+                tokens.push(JsonToken::LBrace);
+                chars.next();
+            }
+            '}' => {
+                tokens.push(JsonToken::RBrace);
+                chars.next();
+            }
+            ':' => {
+                tokens.push(JsonToken::Colon);
+                chars.next();
+            }
+            ',' => {
+                tokens.push(JsonToken::Comma);
+                chars.next();
+            }
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '"' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => match chars.next() {
+                            Some('"') => value.push('"'),
+                            Some('\\') => value.push('\\'),
+                            Some('n') => value.push('\n'),
+                            Some(other) => {
+                                value.push('\\');
+                                value.push(other);
+                            }
+                            None => return Err("unterminated escape in quoted string".to_string()),
+                        },
+                        Some('"') => break,
+                        Some(other) => value.push(other),
+                        None => return Err("unterminated quoted string".to_string()),
+                    }
+                }
+                tokens.push(JsonToken::Str(value));
+            }
+            _ => {
+                let mut literal = String::new();
+                while let Some(&c) = chars.peek() {
+                    if matches!(c, '{' | '}' | ':' | ',' | '"') || c.is_whitespace() {
+                        break;
+                    }
+                    literal.push(c);
+                    chars.next();
+                }
+                tokens.push(JsonToken::Literal(literal));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+// A parsed JSON-ish value: a quoted string, a bare literal (number, or empty when a field was
+// left blank), or a nested object of key/value pairs
+#[derive(Debug, Clone)]
+enum JsonValue {
+    Str(String),
+    Literal(String),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    fn as_str(&self) -> &str {
+        match self {
+            JsonValue::Str(s) => s,
+            JsonValue::Literal(s) => s,
+            JsonValue::Object(_) => "",
+        }
+    }
+    fn as_object(&self) -> Option<&[(String, JsonValue)]> {
+        match self {
+            JsonValue::Object(entries) => Some(entries),
+            _ => None,
+        }
+    }
+}
+
+// Recursive-descent parse of a single value at `tokens[*pos]`, advancing `*pos` past it.
+// A field left blank (a bare `,`/`}` right after the `:`) parses as an empty literal rather
+// than an error, since that's how `due`/`parent` encode "not set".
+fn parse_value(tokens: &[JsonToken], pos: &mut usize) -> Result<JsonValue, String> {
+    match tokens.get(*pos) {
+        Some(JsonToken::Comma) | Some(JsonToken::RBrace) | None => Ok(JsonValue::Literal(String::new())),
+        Some(JsonToken::Str(s)) => {
+            let s = s.clone();
+            *pos += 1;
+            Ok(JsonValue::Str(s))
+        }
+        Some(JsonToken::Literal(s)) => {
+            let s = s.clone();
+            *pos += 1;
+            Ok(JsonValue::Literal(s))
+        }
+        Some(JsonToken::LBrace) => {
+            *pos += 1;
+            let mut entries = Vec::new();
+            loop {
+                match tokens.get(*pos) {
+                    Some(JsonToken::RBrace) => {
+                        *pos += 1;
+                        break;
+                    }
+                    Some(JsonToken::Str(key)) => {
+                        let key = key.clone();
+                        *pos += 1;
+                        match tokens.get(*pos) {
+                            Some(JsonToken::Colon) => *pos += 1,
+                            other => return Err(format!("expected ':' after key, got {:?}", other)),
+                        }
+                        let value = parse_value(tokens, pos)?;
+                        entries.push((key, value));
+                        match tokens.get(*pos) {
+                            Some(JsonToken::Comma) => {
+                                *pos += 1;
+                            }
+                            Some(JsonToken::RBrace) => {}
+                            other => return Err(format!("expected ',' or '}}', got {:?}", other)),
+                        }
+                    }
+                    other => return Err(format!("expected a key or '}}', got {:?}", other)),
+                }
+            }
+            Ok(JsonValue::Object(entries))
+        }
+        other => Err(format!("unexpected token {:?}", other)),
+    }
+}
+
 #[derive(Debug)]
 struct Task {
     id: u32,
@@ -32,6 +345,14 @@ struct Task {
     // For sake of writing/parsing file format and only using the std library, I am going to use the number of seconds since SystemTime::UNIX_EPOCH
     created_at: SystemTime,
     updated_at: SystemTime,
+    time_entries: Vec<TimeEntry>,
+    priority: Priority,
+    tags: HashSet<String>,
+    due: Option<SystemTime>,
+    dependencies: HashSet<u32>,
+    parent: Option<u32>,
+    command: Option<String>,
+    run_history: Vec<RunResult>,
 }
 
 impl std::fmt::Display for Task {
@@ -50,8 +371,40 @@ impl std::fmt::Display for Task {
             };
             time_secs
         };
-        write!(f, "\n\"id\":{0},\n\"description\": \"{1}\",\n\"status\": \"{2}\",\n\"created_at\": {3},\n\"updated_at\": {4}\n", 
-            self.id, self.description, self.status, created_at_since_epoch, updated_at_since_epoch)
+        let mut time_entries = String::new();
+        for (i, entry) in self.time_entries.iter().enumerate() {
+            if i > 0 {
+                time_entries.push(';');
+            }
+            write!(time_entries, "{}", entry)?;
+        }
+        let mut tags: Vec<&String> = self.tags.iter().collect();
+        tags.sort();
+        let tags = tags.iter().map(|t| t.as_str()).collect::<Vec<_>>().join("~");
+        let mut dependencies: Vec<u32> = self.dependencies.iter().copied().collect();
+        dependencies.sort();
+        let dependencies = dependencies
+            .iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>()
+            .join("~");
+        let due = self
+            .due
+            .and_then(|due| due.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs().to_string())
+            .unwrap_or_default();
+        let parent = self.parent.map(|p| p.to_string()).unwrap_or_default();
+        let command = escape_json_string(self.command.as_deref().unwrap_or(""));
+        let mut run_history = String::new();
+        for (i, run) in self.run_history.iter().enumerate() {
+            if i > 0 {
+                run_history.push(';');
+            }
+            write!(run_history, "{}", run)?;
+        }
+        let description = escape_json_string(&self.description);
+        write!(f, "\n\"id\":{0},\n\"description\": \"{1}\",\n\"status\": \"{2}\",\n\"created_at\": {3},\n\"updated_at\": {4},\n\"time_entries\": \"{5}\",\n\"priority\": \"{6}\",\n\"tags\": \"{7}\",\n\"due\": {8},\n\"dependencies\": \"{9}\",\n\"parent\": {10},\n\"command\": \"{11}\",\n\"run_history\": \"{12}\"\n",
+            self.id, description, self.status, created_at_since_epoch, updated_at_since_epoch, time_entries, self.priority, tags, due, dependencies, parent, command, run_history)
     }
 }
 
@@ -59,6 +412,9 @@ impl std::fmt::Display for Task {
 struct TaskHandler {
     tasks: HashMap<u32, Task>,
     updated: bool,
+    // Reused by `list` when no query clauses are given. Persisted alongside the tasks under the
+    // root "default_query" key so it survives across invocations of this one-shot CLI.
+    default_query: ListQuery,
 }
 
 impl std::fmt::Display for TaskHandler {
@@ -79,8 +435,12 @@ impl std::fmt::Display for TaskHandler {
 
 impl TaskHandler {
     // Adds a new task with the first available Id
-    fn add(&mut self, description: String) {
-        let created_at = SystemTime::now();
+    fn add(&mut self, description: String, flags: TaskFlags, at: Option<SystemTime>) {
+        if let Err(unknown) = self.check_dependencies(&flags.dependencies) {
+            println!("Unknown dependency task ID(s): {:?}", unknown);
+            return;
+        }
+        let created_at = at.unwrap_or_else(SystemTime::now);
         let mut id = 0u32;
         while self.tasks.contains_key(&id) {
             id += 1;
@@ -91,14 +451,26 @@ impl TaskHandler {
             status: Status::Todo,
             created_at,
             updated_at: created_at,
+            time_entries: Vec::new(),
+            priority: flags.priority,
+            tags: flags.tags,
+            due: flags.due,
+            dependencies: flags.dependencies,
+            parent: None,
+            command: flags.command,
+            run_history: Vec::new(),
         };
         self.tasks.insert(id, task);
         println!("Task added successfully (ID: {})", id);
         self.updated = true;
     }
     // Updates a task with a given Id
-    fn update(&mut self, id: u32, description: String) {
-        let updated_at = SystemTime::now();
+    fn update(&mut self, id: u32, description: String, flags: TaskFlags, at: Option<SystemTime>) {
+        if let Err(unknown) = self.check_dependencies(&flags.dependencies) {
+            println!("Unknown dependency task ID(s): {:?}", unknown);
+            return;
+        }
+        let updated_at = at.unwrap_or_else(SystemTime::now);
         if let Some(task) = self.tasks.get(&id) {
             let updated_task = Task {
                 id,
@@ -106,6 +478,14 @@ impl TaskHandler {
                 status: task.status,
                 created_at: task.created_at,
                 updated_at,
+                time_entries: task.time_entries.clone(),
+                priority: flags.priority,
+                tags: flags.tags,
+                due: flags.due,
+                dependencies: flags.dependencies,
+                parent: task.parent,
+                command: flags.command,
+                run_history: task.run_history.clone(),
             };
             self.tasks.insert(id, updated_task);
             println!("Task updated successfully (ID: {})", id);
@@ -114,9 +494,42 @@ impl TaskHandler {
             println!("Task not available, please create new task with ID: {}", id);
         }
     }
+    // Returns the subset of the given dependency ids that don't correspond to an existing task
+    fn check_dependencies(&self, dependencies: &HashSet<u32>) -> Result<(), Vec<u32>> {
+        let unknown: Vec<u32> = dependencies
+            .iter()
+            .copied()
+            .filter(|dep| !self.tasks.contains_key(dep))
+            .collect();
+        if unknown.is_empty() { Ok(()) } else { Err(unknown) }
+    }
+    // Drops any dependency id left dangling by a deleted task so we never persist one
+    fn prune_dangling_dependencies(&mut self) {
+        let valid_ids: HashSet<u32> = self.tasks.keys().copied().collect();
+        for task in self.tasks.values_mut() {
+            let before = task.dependencies.len();
+            task.dependencies.retain(|dep| valid_ids.contains(dep));
+            if task.dependencies.len() != before {
+                println!("Dropped dangling dependency on task (ID: {})", task.id);
+            }
+        }
+    }
+    // Clears a child's parent if it points at a task that's since been deleted, so we never
+    // persist one (mirrors prune_dangling_dependencies)
+    fn prune_dangling_parents(&mut self) {
+        let valid_ids: HashSet<u32> = self.tasks.keys().copied().collect();
+        for task in self.tasks.values_mut() {
+            if let Some(parent) = task.parent
+                && !valid_ids.contains(&parent)
+            {
+                task.parent = None;
+                println!("Dropped dangling parent on task (ID: {})", task.id);
+            }
+        }
+    }
     // Updates a task with a given Id to in_progress
-    fn mark_in_progress(&mut self, id: u32) {
-        let updated_at = SystemTime::now();
+    fn mark_in_progress(&mut self, id: u32, at: Option<SystemTime>) {
+        let updated_at = at.unwrap_or_else(SystemTime::now);
         if let Some(task) = self.tasks.get(&id) {
             let updated_task = Task {
                 id,
@@ -124,6 +537,14 @@ impl TaskHandler {
                 status: Status::InProgress,
                 created_at: task.created_at,
                 updated_at,
+                time_entries: task.time_entries.clone(),
+                priority: task.priority,
+                tags: task.tags.clone(),
+                due: task.due,
+                dependencies: task.dependencies.clone(),
+                parent: task.parent,
+                command: task.command.clone(),
+                run_history: task.run_history.clone(),
             };
             self.tasks.insert(id, updated_task);
             println!("Task updated successfully (ID: {})", id);
@@ -133,8 +554,8 @@ impl TaskHandler {
         }
     }
     // Updates a task with a given Id to done
-    fn mark_done(&mut self, id: u32) {
-        let updated_at = SystemTime::now();
+    fn mark_done(&mut self, id: u32, at: Option<SystemTime>) {
+        let updated_at = at.unwrap_or_else(SystemTime::now);
         if let Some(task) = self.tasks.get(&id) {
             let updated_task = Task {
                 id,
@@ -142,6 +563,14 @@ impl TaskHandler {
                 status: Status::Done,
                 created_at: task.created_at,
                 updated_at,
+                time_entries: task.time_entries.clone(),
+                priority: task.priority,
+                tags: task.tags.clone(),
+                due: task.due,
+                dependencies: task.dependencies.clone(),
+                parent: task.parent,
+                command: task.command.clone(),
+                run_history: task.run_history.clone(),
             };
             self.tasks.insert(id, updated_task);
             println!("Task updated successfully (ID: {})", id);
@@ -150,17 +579,218 @@ impl TaskHandler {
             println!("Task not available, please create new task with ID: {}", id);
         }
     }
-    // List the current tasks and can pass an optional filter on todo, in-progress, and done
-    fn list(&self, filter: Option<Status>) {
-        println!("{:<6}{:<30}{:<10}", "id", "description", "status");
-        println!("{:-<46}", "-");
+    // Logs time against a task, rejecting entries whose minutes aren't a valid clock value
+    fn track(&mut self, id: u32, hours: u16, minutes: u16, message: Option<String>) {
+        let duration = match Duration::new(hours, minutes) {
+            Ok(duration) => duration,
+            Err(err) => {
+                println!("Failed to log time: {}", err);
+                return;
+            }
+        };
+        if let Some(task) = self.tasks.get_mut(&id) {
+            match TimeEntry::new(SystemTime::now(), duration, message) {
+                Ok(entry) => {
+                    task.time_entries.push(entry);
+                    println!("Logged {} on task (ID: {})", duration, id);
+                    self.updated = true;
+                }
+                Err(err) => println!("Failed to log time: {}", err),
+            }
+        } else {
+            println!("Task not available, please create new task with ID: {}", id);
+        }
+    }
+    // Runs a task's attached shell command, moving it to in_progress for the duration of the
+    // run and to done if it exits successfully; a non-zero exit leaves it in_progress with the
+    // captured stderr recorded in the task's run history
+    fn run(&mut self, id: u32) {
+        let Some(task) = self.tasks.get_mut(&id) else {
+            println!("Task not available, please create new task with ID: {}", id);
+            return;
+        };
+        let Some(command) = task.command.clone() else {
+            println!("Task has no command attached (ID: {})", id);
+            return;
+        };
+        let description = task.description.clone();
+        task.status = Status::InProgress;
+
+        let run_started = SystemTime::now();
+        let start = Instant::now();
+        let output = std::process::Command::new("sh").arg("-c").arg(&command).output();
+        let duration = start.elapsed();
+
+        let result = match output {
+            Ok(output) => RunResult {
+                run_started,
+                duration,
+                stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+                return_code: output.status.code().unwrap_or(-1),
+            },
+            Err(err) => RunResult {
+                run_started,
+                duration,
+                stdout: String::new(),
+                stderr: err.to_string(),
+                return_code: -1,
+            },
+        };
+
+        let task = self.tasks.get_mut(&id).unwrap();
+        if result.return_code == 0 {
+            task.status = Status::Done;
+        }
+
+        println!("{:<6}{:<30}{:<10}{:<12}", "id", "command", "return", "elapsed");
+        println!("{:-<58}", "-");
+        println!(
+            "{:<6}{:<30}{:<10}{:<12}",
+            id,
+            command,
+            result.return_code,
+            format!("{:.2?}", duration)
+        );
+        if result.return_code != 0 {
+            println!("{} stderr: {}", description, result.stderr.trim());
+        }
+
+        task.run_history.push(result);
+        self.updated = true;
+    }
+    // Links a task under a parent (or back to the root of all tasks with `parent: None`),
+    // rejecting a link that would create a cycle by walking the proposed parent's own chain
+    fn set_parent(&mut self, child: u32, parent: Option<u32>) {
+        if !self.tasks.contains_key(&child) {
+            println!("Task not available, please create new task with ID: {}", child);
+            return;
+        }
+        if let Some(parent_id) = parent {
+            if !self.tasks.contains_key(&parent_id) {
+                println!("Task not available, please create new task with ID: {}", parent_id);
+                return;
+            }
+            let mut current = Some(parent_id);
+            while let Some(id) = current {
+                if id == child {
+                    println!("Cannot set parent: would create a cycle");
+                    return;
+                }
+                current = self.tasks.get(&id).and_then(|task| task.parent);
+            }
+        }
+        self.tasks.get_mut(&child).unwrap().parent = parent;
+        self.updated = true;
+        match parent {
+            Some(parent_id) => println!("Task (ID: {}) is now a subtask of (ID: {})", child, parent_id),
+            None => println!("Task (ID: {}) moved to the root of all tasks", child),
+        }
+    }
+    // List the current tasks, filtered and ordered by the given query, with subtasks indented
+    // depth-first under their parent
+    fn list(&self, query: &ListQuery) {
+        let visible: HashSet<u32> = self
+            .tasks
+            .values()
+            .filter(|task| self.matches_query(task, query))
+            .map(|task| task.id)
+            .collect();
+
+        let mut children: HashMap<Option<u32>, Vec<&Task>> = HashMap::new();
         for task in self.tasks.values() {
-            if let Some(status) = filter
-                && task.status != status
-            {
+            if !visible.contains(&task.id) {
                 continue;
             }
-            println!("{:<6}{:<30}{:<10}", task.id, task.description, task.status);
+            let effective_parent = task.parent.filter(|parent| visible.contains(parent));
+            children.entry(effective_parent).or_default().push(task);
+        }
+        for siblings in children.values_mut() {
+            siblings.sort_by(|a, b| Self::compare_tasks(a, b, query.order));
+            if query.direction == SortDirection::Desc {
+                siblings.reverse();
+            }
+        }
+
+        println!("{:<6}{:<30}{:<10}", "id", "description", "status");
+        println!("{:-<46}", "-");
+        self.print_children(&children, None, 0);
+    }
+    // Depth-first walk of the parent/children map built by `list`
+    fn print_children(&self, children: &HashMap<Option<u32>, Vec<&Task>>, parent: Option<u32>, depth: usize) {
+        let Some(siblings) = children.get(&parent) else {
+            return;
+        };
+        for task in siblings {
+            let description = format!("{}{}", "  ".repeat(depth), task.description);
+            println!("{:<6}{:<30}{:<10}", task.id, description, self.effective_status(task));
+            self.print_children(children, Some(task.id), depth + 1);
+        }
+    }
+    // A task only displays as done if every descendant is also done
+    fn effective_status(&self, task: &Task) -> Status {
+        if task.status == Status::Done && !self.all_descendants_done(task.id) {
+            Status::InProgress
+        } else {
+            task.status
+        }
+    }
+    fn all_descendants_done(&self, id: u32) -> bool {
+        self.tasks
+            .values()
+            .filter(|task| task.parent == Some(id))
+            .all(|child| child.status == Status::Done && self.all_descendants_done(child.id))
+    }
+    // Whether a task satisfies every clause in a query
+    fn matches_query(&self, task: &Task, query: &ListQuery) -> bool {
+        if !query.status.is_empty() && !query.status.contains(&task.status) {
+            return false;
+        }
+        if let Some(priority) = query.priority
+            && task.priority != priority
+        {
+            return false;
+        }
+        if let Some(tag) = &query.tag
+            && !task.tags.contains(tag)
+        {
+            return false;
+        }
+        if query.actionable_only && !self.dependencies_done(task) {
+            return false;
+        }
+        true
+    }
+    // A task is actionable once every task it depends on is Done
+    fn dependencies_done(&self, task: &Task) -> bool {
+        task.dependencies.iter().all(|dep| {
+            self.tasks
+                .get(dep)
+                .map(|dependency| dependency.status == Status::Done)
+                .unwrap_or(false)
+        })
+    }
+    fn compare_tasks(a: &Task, b: &Task, order: OrderKey) -> std::cmp::Ordering {
+        match order {
+            OrderKey::Id => a.id.cmp(&b.id),
+            OrderKey::CreatedAt => a.created_at.cmp(&b.created_at),
+            OrderKey::UpdatedAt => a.updated_at.cmp(&b.updated_at),
+            OrderKey::Due => a.due.cmp(&b.due),
+            OrderKey::Priority => a.priority.cmp(&b.priority),
+        }
+    }
+    // Prints total logged time per task, summed from its time entries
+    fn time_summary(&self) {
+        println!("{:<6}{:<30}{:<10}", "id", "description", "logged");
+        println!("{:-<46}", "-");
+        for task in self.tasks.values() {
+            let total_minutes: u32 = task
+                .time_entries
+                .iter()
+                .map(|entry| entry.duration.hours as u32 * 60 + entry.duration.minutes as u32)
+                .sum();
+            let logged = format!("{}h{}m", total_minutes / 60, total_minutes % 60);
+            println!("{:<6}{:<30}{:<10}", task.id, task.description, logged);
         }
     }
     // Deletes a task if it does exist with a given id
@@ -172,107 +802,526 @@ impl TaskHandler {
             println!("Task failed to delete or does not exist (ID: {})", id);
         }
     }
+    // Parses a `logged_at~<hours>h<minutes>m~message` encoded time entry, as produced by TimeEntry's Display
+    fn parse_time_entry(raw: &str) -> Result<TimeEntry, String> {
+        let fields: Vec<&str> = raw.splitn(3, '~').collect();
+        let logged_at_secs = fields
+            .first()
+            .ok_or("time entry missing logged_at")?
+            .parse::<u64>()
+            .map_err(|e| format!("invalid logged_at in time entry: {}", e))?;
+        let logged_at = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(logged_at_secs);
+        let duration_str = fields.get(1).ok_or("time entry missing duration")?;
+        let (hours_str, minutes_str) = duration_str
+            .split_once('h')
+            .ok_or("invalid duration in time entry")?;
+        let minutes_str = minutes_str.trim_end_matches('m');
+        let hours = hours_str
+            .parse::<u16>()
+            .map_err(|e| format!("invalid hours in time entry: {}", e))?;
+        let minutes = minutes_str
+            .parse::<u16>()
+            .map_err(|e| format!("invalid minutes in time entry: {}", e))?;
+        let message = fields.get(2).filter(|m| !m.is_empty()).map(|m| m.to_string());
+        let duration = Duration::new(hours, minutes)?;
+        TimeEntry::new(logged_at, duration, message)
+    }
+    // Parses a `run_started~duration~return_code~stdout~stderr` encoded run result, as produced
+    // by RunResult's Display
+    fn parse_run_result(raw: &str) -> Result<RunResult, String> {
+        let fields: Vec<&str> = raw.splitn(5, '~').collect();
+        let run_started_secs = fields
+            .first()
+            .ok_or("run result missing run_started")?
+            .parse::<u64>()
+            .map_err(|e| format!("invalid run_started in run result: {}", e))?;
+        let run_started = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(run_started_secs);
+        let duration = fields
+            .get(1)
+            .ok_or("run result missing duration")?
+            .parse::<u64>()
+            .map(std::time::Duration::from_secs)
+            .map_err(|e| format!("invalid duration in run result: {}", e))?;
+        let return_code = fields
+            .get(2)
+            .ok_or("run result missing return_code")?
+            .parse::<i32>()
+            .map_err(|e| format!("invalid return_code in run result: {}", e))?;
+        let stdout = fields.get(3).copied().unwrap_or("").to_string();
+        let stderr = fields.get(4).copied().unwrap_or("").to_string();
+        Ok(RunResult {
+            run_started,
+            duration,
+            stdout,
+            stderr,
+            return_code,
+        })
+    }
+    // Builds a Task from a task object's key/value pairs, in whatever order they appear
+    fn build_task(id: u32, fields: &[(String, JsonValue)]) -> Result<Task, String> {
+        let field = |key: &str| fields.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+
+        let inner_id = field("id")
+            .ok_or("task missing id")?
+            .as_str()
+            .parse::<u32>()
+            .map_err(|e| format!("invalid id: {}", e))?;
+        if id != inner_id {
+            println!("id and inner id don't match");
+        }
+        let description = field("description").ok_or("task missing description")?.as_str().to_string();
+        let status = match field("status").ok_or("task missing status")?.as_str() {
+            "todo" => Status::Todo,
+            "in-progress" => Status::InProgress,
+            "done" => Status::Done,
+            other => return Err(format!("invalid status: {}", other)),
+        };
+        let created_at = field("created_at")
+            .ok_or("task missing created_at")?
+            .as_str()
+            .parse::<u64>()
+            .map(|t| SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(t))
+            .map_err(|e| format!("invalid created_at: {}", e))?;
+        let updated_at = field("updated_at")
+            .ok_or("task missing updated_at")?
+            .as_str()
+            .parse::<u64>()
+            .map(|t| SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(t))
+            .map_err(|e| format!("invalid updated_at: {}", e))?;
+        let time_entries = field("time_entries")
+            .map(|v| v.as_str())
+            .unwrap_or("")
+            .split(';')
+            .filter(|e| !e.is_empty())
+            .map(Self::parse_time_entry)
+            .collect::<Result<Vec<_>, _>>()?;
+        let priority = match field("priority").map(|v| v.as_str()).unwrap_or("") {
+            "low" => Priority::Low,
+            "medium" => Priority::Medium,
+            "high" => Priority::High,
+            _ => Priority::default(),
+        };
+        let tags = field("tags")
+            .map(|v| v.as_str())
+            .unwrap_or("")
+            .split('~')
+            .filter(|t| !t.is_empty())
+            .map(|t| t.to_string())
+            .collect();
+        let due = {
+            let due = field("due").map(|v| v.as_str()).unwrap_or("");
+            if due.is_empty() {
+                None
+            } else {
+                Some(
+                    due.parse::<u64>()
+                        .map(|t| SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(t))
+                        .map_err(|e| format!("invalid due: {}", e))?,
+                )
+            }
+        };
+        let dependencies = field("dependencies")
+            .map(|v| v.as_str())
+            .unwrap_or("")
+            .split('~')
+            .filter(|d| !d.is_empty())
+            .map(|d| d.parse::<u32>().map_err(|e| format!("invalid dependency: {}", e)))
+            .collect::<Result<HashSet<_>, _>>()?;
+        let parent = {
+            let parent = field("parent").map(|v| v.as_str()).unwrap_or("");
+            if parent.is_empty() {
+                None
+            } else {
+                Some(parent.parse::<u32>().map_err(|e| format!("invalid parent: {}", e))?)
+            }
+        };
+        let command = field("command")
+            .map(|v| v.as_str())
+            .filter(|c| !c.is_empty())
+            .map(|c| c.to_string());
+        let run_history = field("run_history")
+            .map(|v| v.as_str())
+            .unwrap_or("")
+            .split(';')
+            .filter(|r| !r.is_empty())
+            .map(Self::parse_run_result)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Task {
+            id,
+            description,
+            status,
+            created_at,
+            updated_at,
+            time_entries,
+            priority,
+            tags,
+            due,
+            dependencies,
+            parent,
+            command,
+            run_history,
+        })
+    }
+    // Parses the full `{ "tasks": { "<id>": { ... }, ... } }` contents of tasks.json
+    fn parse_tasks(data: &str) -> Result<HashMap<u32, Task>, String> {
+        let tokens = tokenize(data)?;
+        let mut pos = 0;
+        let root = parse_value(&tokens, &mut pos)?;
+        let root = root.as_object().ok_or("expected a top-level object")?;
+        let tasks_obj = root
+            .iter()
+            .find(|(k, _)| k == "tasks")
+            .map(|(_, v)| v)
+            .ok_or("missing \"tasks\" key")?
+            .as_object()
+            .ok_or("\"tasks\" must be an object")?;
+
+        let mut tasks = HashMap::new();
+        for (key, value) in tasks_obj {
+            let id = key.parse::<u32>().map_err(|e| format!("invalid task key {:?}: {}", key, e))?;
+            let fields = value.as_object().ok_or("task entry must be an object")?;
+            let task = Self::build_task(id, fields)?;
+            tasks.insert(id, task);
+        }
+        Ok(tasks)
+    }
+    // Reads the root "default_query" key left by a previous `list-default`; absent or unparseable
+    // defaults to ListQuery::default() rather than failing the whole file load
+    fn parse_default_query(data: &str) -> ListQuery {
+        let Ok(tokens) = tokenize(data) else {
+            return ListQuery::default();
+        };
+        let mut pos = 0;
+        let Ok(root) = parse_value(&tokens, &mut pos) else {
+            return ListQuery::default();
+        };
+        let Some(root) = root.as_object() else {
+            return ListQuery::default();
+        };
+        root.iter()
+            .find(|(k, _)| k == "default_query")
+            .map(|(_, v)| decode_list_query(v.as_str()))
+            .unwrap_or_default()
+    }
     // Everytime the command is run, the tasks.json file is parsed to provide the latest
     fn query_task_file() -> Self {
-        let mut tasks = HashMap::new();
+        let data = read_to_string("tasks.json").ok();
+        let tasks = match &data {
+            Some(data) => match Self::parse_tasks(data) {
+                Ok(tasks) => tasks,
+                Err(err) => {
+                    println!("Failed to parse tasks.json, starting with no tasks: {}", err);
+                    HashMap::new()
+                }
+            },
+            None => HashMap::new(),
+        };
+        let default_query = data.as_deref().map(Self::parse_default_query).unwrap_or_default();
 
-        if let Ok(data) = read_to_string("tasks.json") {
-            let data = data.replace("\"", "");
-            let parts: Vec<&str> = data
-                .split(&['{', '}'][..])
-                .map(|p| p.trim().trim_end_matches(':').trim())
-                .filter(|p| !p.is_empty())
-                .collect();
-            let mut id = 0;
-            for (i, id_task) in parts.iter().skip(1).enumerate() {
-                if i % 2 == 1 {
-                    let task_parts: Vec<&str> = id_task
-                        .split(',')
-                        .map(|p| p.trim().split(':').collect::<Vec<_>>()[1])
-                        .collect();
-                    let inner_id = task_parts[0]
-                        .trim()
-                        .parse::<u32>()
-                        .expect("failed to convert id from json");
-                    if id != inner_id {
-                        println!("id and inner id don't match");
+        TaskHandler {
+            tasks,
+            updated: false,
+            default_query,
+        }
+    }
+    // Writes tasks.json atomically: the new contents land in a temp file first, which is then
+    // renamed over the real file so a crash mid-write can never leave a corrupt tasks.json
+    fn save(&self) -> io::Result<()> {
+        let tmp_path = "tasks.json.tmp";
+        let mut file = File::create(tmp_path)?;
+        let default_query = escape_json_string(&encode_list_query(&self.default_query));
+        writeln!(
+            file,
+            "{{\n \"tasks\": {{ {} }},\n \"default_query\": \"{}\" \n }}",
+            self, default_query
+        )?;
+        file.sync_all()?;
+        std::fs::rename(tmp_path, "tasks.json")?;
+        Ok(())
+    }
+}
+
+// The optional `--priority/--tag/--due/--depends/--command` flags accepted by `add`/`update`
+#[derive(Default)]
+struct TaskFlags {
+    priority: Priority,
+    tags: HashSet<String>,
+    due: Option<SystemTime>,
+    dependencies: HashSet<u32>,
+    command: Option<String>,
+}
+
+// Scans the flag tokens following a command's positional arguments
+fn parse_task_flags(args: &[String]) -> TaskFlags {
+    let mut flags = TaskFlags::default();
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--priority" => {
+                if let Some(value) = args.get(i + 1) {
+                    flags.priority = match value.as_str() {
+                        "low" => Priority::Low,
+                        "medium" => Priority::Medium,
+                        "high" => Priority::High,
+                        _ => {
+                            println!("Not a valid priority, defaulting to medium");
+                            Priority::Medium
+                        }
+                    };
+                }
+                i += 2;
+            }
+            "--tag" => {
+                if let Some(value) = args.get(i + 1) {
+                    flags.tags.insert(value.clone());
+                }
+                i += 2;
+            }
+            "--due" => {
+                if let Some(value) = args.get(i + 1) {
+                    match value.parse::<u64>() {
+                        Ok(secs) => {
+                            flags.due = Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs))
+                        }
+                        Err(_) => println!("Not a valid due timestamp"),
                     }
-                    let description = task_parts[1]
-                        .trim()
-                        .parse::<String>()
-                        .expect("failed to convert description from json");
-                    let status = task_parts[2]
-                        .trim()
-                        .parse::<String>()
-                        .map(|s| match s.as_str() {
-                            "todo" => Status::Todo,
-                            "in-progress" => Status::InProgress,
-                            "done" => Status::Done,
-                            _ => Status::Todo,
-                        })
-                        .expect("failed to convert status from json");
-                    let created_at = task_parts[3]
-                        .trim()
-                        .parse::<u64>()
-                        .map(|t| SystemTime::UNIX_EPOCH + Duration::from_secs(t))
-                        .expect("failed to convert created_at from json");
-                    let updated_at = task_parts[4]
-                        .trim()
-                        .parse::<u64>()
-                        .map(|t| SystemTime::UNIX_EPOCH + Duration::from_secs(t))
-                        .expect("failed to convert updated_at from json");
-
-                    let task = Task {
-                        id,
-                        description,
-                        status,
-                        created_at,
-                        updated_at,
+                }
+                i += 2;
+            }
+            "--depends" => {
+                if let Some(value) = args.get(i + 1) {
+                    match value.parse::<u32>() {
+                        Ok(dep_id) => {
+                            flags.dependencies.insert(dep_id);
+                        }
+                        Err(_) => println!("Not a valid dependency id"),
+                    }
+                }
+                i += 2;
+            }
+            "--command" => {
+                if let Some(value) = args.get(i + 1) {
+                    flags.command = Some(value.clone());
+                }
+                i += 2;
+            }
+            _ => i += 1,
+        }
+    }
+    flags
+}
+
+// Parses `list`-style clauses such as `status:done,in-progress priority:high tag:work
+// order:due sort:asc actionable` into a ListQuery
+fn parse_list_query(args: &[String]) -> ListQuery {
+    let mut query = ListQuery::default();
+    for arg in args {
+        match arg.split_once(':') {
+            Some(("status", value)) => {
+                for status in value.split(',') {
+                    match status {
+                        "todo" => query.status.insert(Status::Todo),
+                        "in-progress" => query.status.insert(Status::InProgress),
+                        "done" => query.status.insert(Status::Done),
+                        _ => {
+                            println!("Not a valid status for query: {}", status);
+                            false
+                        }
                     };
-                    tasks.insert(id, task);
-                } else {
-                    id = id_task
-                        .trim_matches(',')
-                        .trim()
-                        .parse::<u32>()
-                        .expect("failed to convert id from key");
                 }
             }
+            Some(("priority", value)) => {
+                query.priority = match value {
+                    "low" => Some(Priority::Low),
+                    "medium" => Some(Priority::Medium),
+                    "high" => Some(Priority::High),
+                    _ => {
+                        println!("Not a valid priority for query: {}", value);
+                        None
+                    }
+                };
+            }
+            Some(("tag", value)) => query.tag = Some(value.to_string()),
+            Some(("order", value)) => {
+                query.order = match value {
+                    "id" => OrderKey::Id,
+                    "created_at" => OrderKey::CreatedAt,
+                    "updated_at" => OrderKey::UpdatedAt,
+                    "due" => OrderKey::Due,
+                    "priority" => OrderKey::Priority,
+                    _ => {
+                        println!("Not a valid order key for query: {}", value);
+                        OrderKey::Id
+                    }
+                };
+            }
+            Some(("sort", value)) => {
+                query.direction = match value {
+                    "asc" => SortDirection::Asc,
+                    "desc" => SortDirection::Desc,
+                    _ => {
+                        println!("Not a valid sort direction for query: {}", value);
+                        SortDirection::Asc
+                    }
+                };
+            }
+            _ if arg == "actionable" => query.actionable_only = true,
+            _ => println!("Unknown query clause: {}", arg),
         }
+    }
+    query
+}
 
-        TaskHandler {
-            tasks,
-            updated: false,
+// Encodes a ListQuery using the same `~`-joined convention as other compound fields, so it can be
+// stored as a single string under the root "default_query" key (multiple statuses join with `+`)
+fn encode_list_query(query: &ListQuery) -> String {
+    let status = query
+        .status
+        .iter()
+        .map(|status| match status {
+            Status::Todo => "todo",
+            Status::InProgress => "in-progress",
+            Status::Done => "done",
+        })
+        .collect::<Vec<_>>()
+        .join("+");
+    let priority = query.priority.map(|p| p.to_string()).unwrap_or_default();
+    let tag = query.tag.as_deref().unwrap_or("");
+    let order = match query.order {
+        OrderKey::Id => "id",
+        OrderKey::CreatedAt => "created_at",
+        OrderKey::UpdatedAt => "updated_at",
+        OrderKey::Due => "due",
+        OrderKey::Priority => "priority",
+    };
+    let direction = match query.direction {
+        SortDirection::Asc => "asc",
+        SortDirection::Desc => "desc",
+    };
+    format!(
+        "{}~{}~{}~{}~{}~{}",
+        status, priority, tag, order, direction, query.actionable_only
+    )
+}
+
+// Inverse of `encode_list_query`; unknown/malformed clauses fall back to their ListQuery::default()
+fn decode_list_query(raw: &str) -> ListQuery {
+    let mut fields = raw.split('~');
+    let mut query = ListQuery::default();
+    if let Some(status) = fields.next() {
+        for s in status.split('+').filter(|s| !s.is_empty()) {
+            match s {
+                "todo" => query.status.insert(Status::Todo),
+                "in-progress" => query.status.insert(Status::InProgress),
+                "done" => query.status.insert(Status::Done),
+                _ => false,
+            };
         }
     }
+    query.priority = fields.next().and_then(|p| match p {
+        "low" => Some(Priority::Low),
+        "medium" => Some(Priority::Medium),
+        "high" => Some(Priority::High),
+        _ => None,
+    });
+    query.tag = fields.next().filter(|t| !t.is_empty()).map(|t| t.to_string());
+    query.order = match fields.next() {
+        Some("created_at") => OrderKey::CreatedAt,
+        Some("updated_at") => OrderKey::UpdatedAt,
+        Some("due") => OrderKey::Due,
+        Some("priority") => OrderKey::Priority,
+        _ => OrderKey::Id,
+    };
+    query.direction = match fields.next() {
+        Some("desc") => SortDirection::Desc,
+        _ => SortDirection::Asc,
+    };
+    query.actionable_only = fields.next() == Some("true");
+    query
+}
+
+// Resolves a `@<timestamp>` token's payload: an absolute unix timestamp, or a relative
+// `-<seconds>` offset counting back from now (e.g. `-3600` for an hour ago)
+fn parse_backdate(token: &str) -> Result<SystemTime, String> {
+    if let Some(offset) = token.strip_prefix('-') {
+        let secs = offset
+            .parse::<u64>()
+            .map_err(|e| format!("relative backdate must be a number of seconds: {}", e))?;
+        Ok(SystemTime::now() - std::time::Duration::from_secs(secs))
+    } else {
+        let secs = token
+            .parse::<u64>()
+            .map_err(|e| format!("backdate must be a unix timestamp: {}", e))?;
+        Ok(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs))
+    }
+}
+
+// The minimum `args_passed` count (command name plus its required positional args) each
+// backdatable mutating command needs, once a trailing `@TIME` token is removed
+fn min_args_for_backdate(command: &str) -> Option<usize> {
+    match command {
+        "add" => Some(2),
+        "update" => Some(3),
+        "mark-in-progress" | "mark-done" | "delete" => Some(2),
+        _ => None,
+    }
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+
+    // A trailing `@TIME` token backdates add/update/mark-in-progress/mark-done instead of
+    // stamping SystemTime::now(); delete also accepts and strips one, though a removed task keeps
+    // no timestamp to set. The token is only taken once that command's required positional args
+    // are otherwise satisfied and when it isn't the value of a `--flag`, so a description, tag,
+    // or message starting with `@` (e.g. "@meeting") is never mistaken for a backdate token.
+    let mut at = None;
+    let mut backdate_error = None;
+    if let Some(token) = args.last().and_then(|last| last.strip_prefix('@')) {
+        let recognized = args
+            .get(1)
+            .and_then(|command| min_args_for_backdate(command))
+            .is_some_and(|min_args| args.len() >= min_args + 2)
+            && !args[args.len() - 2].starts_with("--");
+        if recognized {
+            match parse_backdate(token) {
+                Ok(resolved) => at = Some(resolved),
+                Err(err) => backdate_error = Some(err),
+            }
+        }
+    }
+    if at.is_some() || backdate_error.is_some() {
+        args.pop();
+    }
 
     let mut tasks = TaskHandler::query_task_file();
 
     let args_passed = args.len() - 1;
 
-    if args_passed > 0 {
+    if let Some(err) = backdate_error {
+        println!("Invalid backdate token: {}", err);
+    } else if args_passed > 0 {
         // map the required number of arguments with the available
         // this part could be done in different ways but this was a quick one to setup with the
         // small number of arguments
         // failure here are related to the parsing argument process before handling it to the
         // function
         match (args[1].as_str(), args_passed) {
-            ("add", 2) => {
+            ("add", n) if n >= 2 => {
                 if let Some(task_description) = args.get(2) {
-                    tasks.add(task_description.to_string());
+                    let flags = parse_task_flags(&args[3..]);
+                    tasks.add(task_description.to_string(), flags, at);
                 } else {
                     println!("failed to parse task");
                 }
             }
-            ("update", 3) => {
+            ("update", n) if n >= 3 => {
                 if let Some(id) = args.get(2) {
                     if let Some(updated_task_description) = args.get(3) {
                         let id = id.parse::<u32>().expect("id must be a number");
-                        tasks.update(id, updated_task_description.to_string());
+                        let flags = parse_task_flags(&args[4..]);
+                        tasks.update(id, updated_task_description.to_string(), flags, at);
                     } else {
                         println!("failed to parse updated task");
                     }
@@ -288,27 +1337,36 @@ fn main() {
                     println!("failed to parse id");
                 }
             }
-            ("list", 1..=2) => {
-                // Not the best work to achieve this but might come back to it later
-                let filter = match args.get(2) {
-                    Some(status) => match status.as_str() {
-                        "done" => Some(Status::Done),
-                        "todo" => Some(Status::Todo),
-                        "in-progress" => Some(Status::InProgress),
-                        _ => {
-                            println!("Not a valid status for task");
-                            None
-                        }
-                    },
-                    None => None,
+            ("list", n) if n >= 1 => {
+                let query = if n > 1 {
+                    parse_list_query(&args[2..])
+                } else {
+                    tasks.default_query.clone()
                 };
-
-                tasks.list(filter);
+                tasks.list(&query);
+            }
+            ("list-default", n) if n >= 1 => {
+                tasks.default_query = parse_list_query(&args[2..]);
+                tasks.updated = true;
+                println!("Default list query updated");
+            }
+            ("set-parent", 3) => {
+                if let (Some(child), Some(parent)) = (args.get(2), args.get(3)) {
+                    let child = child.parse::<u32>().expect("id must be a number");
+                    let parent = if parent == "root" {
+                        None
+                    } else {
+                        Some(parent.parse::<u32>().expect("id must be a number"))
+                    };
+                    tasks.set_parent(child, parent);
+                } else {
+                    println!("failed to parse set-parent arguments");
+                }
             }
             ("mark-in-progress", 2) => {
                 if let Some(id) = args.get(2) {
                     let id = id.parse::<u32>().expect("id must be a number");
-                    tasks.mark_in_progress(id);
+                    tasks.mark_in_progress(id, at);
                 } else {
                     println!("failed to parse id");
                 }
@@ -316,7 +1374,31 @@ fn main() {
             ("mark-done", 2) => {
                 if let Some(id) = args.get(2) {
                     let id = id.parse::<u32>().expect("id must be a number");
-                    tasks.mark_done(id);
+                    tasks.mark_done(id, at);
+                } else {
+                    println!("failed to parse id");
+                }
+            }
+            ("track", 4..=5) => {
+                if let (Some(id), Some(hours), Some(minutes)) =
+                    (args.get(2), args.get(3), args.get(4))
+                {
+                    let id = id.parse::<u32>().expect("id must be a number");
+                    let hours = hours.parse::<u16>().expect("hours must be a number");
+                    let minutes = minutes.parse::<u16>().expect("minutes must be a number");
+                    let message = args.get(5).cloned();
+                    tasks.track(id, hours, minutes, message);
+                } else {
+                    println!("failed to parse track arguments");
+                }
+            }
+            ("time-summary", 1) => {
+                tasks.time_summary();
+            }
+            ("run", 2) => {
+                if let Some(id) = args.get(2) {
+                    let id = id.parse::<u32>().expect("id must be a number");
+                    tasks.run(id);
                 } else {
                     println!("failed to parse id");
                 }
@@ -329,10 +1411,11 @@ fn main() {
         println!("Invalid amount of arguments, must provide one argument for action");
     }
     if tasks.updated {
-        // Print to file
-        // Relies on the fmt of the TaskHandler and Tasks to produce valid json
-        let mut file = File::create("tasks.json").expect("failed to create file");
-        let _ = writeln!(file, "{{\n \"tasks\": {{ {} }} \n }}", tasks);
+        tasks.prune_dangling_dependencies();
+        tasks.prune_dangling_parents();
+        if let Err(err) = tasks.save() {
+            println!("Failed to save tasks: {}", err);
+        }
     }
 }
 
@@ -351,7 +1434,7 @@ mod tests {
     #[test]
     fn add_task() {
         let mut task_handler = TaskHandler::default();
-        task_handler.add(String::from("task_a"));
+        task_handler.add(String::from("task_a"), TaskFlags { priority: Priority::default(), tags: HashSet::new(), due: None, dependencies: HashSet::new(), command: None }, None);
         let made_task = task_handler.tasks.get(&0).unwrap();
         assert_eq!(0, made_task.id);
         assert_eq!("task_a", made_task.description);
@@ -360,8 +1443,8 @@ mod tests {
     #[test]
     fn update_task() {
         let mut task_handler = TaskHandler::default();
-        task_handler.add(String::from("task_a"));
-        task_handler.update(0, String::from("task_b"));
+        task_handler.add(String::from("task_a"), TaskFlags { priority: Priority::default(), tags: HashSet::new(), due: None, dependencies: HashSet::new(), command: None }, None);
+        task_handler.update(0, String::from("task_b"), TaskFlags { priority: Priority::default(), tags: HashSet::new(), due: None, dependencies: HashSet::new(), command: None }, None);
         let made_task = task_handler.tasks.get(&0).unwrap();
         assert_eq!(0, made_task.id);
         assert_eq!("task_b", made_task.description);
@@ -370,15 +1453,15 @@ mod tests {
     #[test]
     fn delete_task() {
         let mut task_handler = TaskHandler::default();
-        task_handler.add(String::from("task_a"));
+        task_handler.add(String::from("task_a"), TaskFlags { priority: Priority::default(), tags: HashSet::new(), due: None, dependencies: HashSet::new(), command: None }, None);
         task_handler.delete(0);
         assert!(task_handler.tasks.is_empty());
     }
     #[test]
     fn mark_task_in_progress() {
         let mut task_handler = TaskHandler::default();
-        task_handler.add(String::from("task_a"));
-        task_handler.mark_in_progress(0);
+        task_handler.add(String::from("task_a"), TaskFlags { priority: Priority::default(), tags: HashSet::new(), due: None, dependencies: HashSet::new(), command: None }, None);
+        task_handler.mark_in_progress(0, None);
         let made_task = task_handler.tasks.get(&0).unwrap();
         assert_eq!(0, made_task.id);
         assert_eq!("task_a", made_task.description);
@@ -387,11 +1470,295 @@ mod tests {
     #[test]
     fn mark_task_done() {
         let mut task_handler = TaskHandler::default();
-        task_handler.add(String::from("task_a"));
-        task_handler.mark_done(0);
+        task_handler.add(String::from("task_a"), TaskFlags { priority: Priority::default(), tags: HashSet::new(), due: None, dependencies: HashSet::new(), command: None }, None);
+        task_handler.mark_done(0, None);
         let made_task = task_handler.tasks.get(&0).unwrap();
         assert_eq!(0, made_task.id);
         assert_eq!("task_a", made_task.description);
         assert_eq!(Status::Done, made_task.status);
     }
+    #[test]
+    fn track_logs_time_against_task() {
+        let mut task_handler = TaskHandler::default();
+        task_handler.add(String::from("task_a"), TaskFlags { priority: Priority::default(), tags: HashSet::new(), due: None, dependencies: HashSet::new(), command: None }, None);
+        task_handler.track(0, 1, 30, Some(String::from("half done")));
+        let made_task = task_handler.tasks.get(&0).unwrap();
+        assert_eq!(1, made_task.time_entries.len());
+        assert_eq!(Duration { hours: 1, minutes: 30 }, made_task.time_entries[0].duration);
+    }
+    #[test]
+    fn track_rejects_invalid_minutes() {
+        let mut task_handler = TaskHandler::default();
+        task_handler.add(String::from("task_a"), TaskFlags { priority: Priority::default(), tags: HashSet::new(), due: None, dependencies: HashSet::new(), command: None }, None);
+        task_handler.track(0, 1, 60, None);
+        let made_task = task_handler.tasks.get(&0).unwrap();
+        assert!(made_task.time_entries.is_empty());
+    }
+    #[test]
+    fn add_task_with_metadata() {
+        let mut task_handler = TaskHandler::default();
+        let tags = HashSet::from([String::from("work")]);
+        task_handler.add(String::from("task_a"), TaskFlags { priority: Priority::High, tags: tags.clone(), due: None, dependencies: HashSet::new(), command: None }, None);
+        let made_task = task_handler.tasks.get(&0).unwrap();
+        assert_eq!(Priority::High, made_task.priority);
+        assert_eq!(tags, made_task.tags);
+    }
+    #[test]
+    fn add_task_rejects_unknown_dependency() {
+        let mut task_handler = TaskHandler::default();
+        let dependencies = HashSet::from([99]);
+        task_handler.add(String::from("task_a"), TaskFlags { priority: Priority::default(), tags: HashSet::new(), due: None, dependencies: dependencies, command: None }, None);
+        assert!(task_handler.tasks.is_empty());
+    }
+    #[test]
+    fn prune_dangling_dependencies_drops_deleted_task() {
+        let mut task_handler = TaskHandler::default();
+        task_handler.add(String::from("task_a"), TaskFlags { priority: Priority::default(), tags: HashSet::new(), due: None, dependencies: HashSet::new(), command: None }, None);
+        let dependencies = HashSet::from([0]);
+        task_handler.add(String::from("task_b"), TaskFlags { priority: Priority::default(), tags: HashSet::new(), due: None, dependencies: dependencies, command: None }, None);
+        task_handler.delete(0);
+        task_handler.prune_dangling_dependencies();
+        let made_task = task_handler.tasks.get(&1).unwrap();
+        assert!(made_task.dependencies.is_empty());
+    }
+    #[test]
+    fn prune_dangling_parents_clears_deleted_parent() {
+        let mut task_handler = TaskHandler::default();
+        task_handler.add(String::from("parent"), TaskFlags::default(), None);
+        task_handler.add(String::from("child"), TaskFlags::default(), None);
+        task_handler.set_parent(1, Some(0));
+        task_handler.delete(0);
+        task_handler.prune_dangling_parents();
+        let child = task_handler.tasks.get(&1).unwrap();
+        assert_eq!(None, child.parent);
+    }
+    #[test]
+    fn query_filters_by_status_and_priority() {
+        let mut task_handler = TaskHandler::default();
+        task_handler.add(String::from("task_a"), TaskFlags { priority: Priority::High, tags: HashSet::new(), due: None, dependencies: HashSet::new(), command: None }, None);
+        task_handler.add(String::from("task_b"), TaskFlags { priority: Priority::Low, tags: HashSet::new(), due: None, dependencies: HashSet::new(), command: None }, None);
+        task_handler.mark_done(1, None);
+        let query = ListQuery {
+            status: HashSet::from([Status::Done]),
+            priority: Some(Priority::Low),
+            ..ListQuery::default()
+        };
+        let matching: Vec<u32> = task_handler
+            .tasks
+            .values()
+            .filter(|task| task_handler.matches_query(task, &query))
+            .map(|task| task.id)
+            .collect();
+        assert_eq!(vec![1], matching);
+    }
+    #[test]
+    fn query_actionable_requires_done_dependencies() {
+        let mut task_handler = TaskHandler::default();
+        task_handler.add(String::from("task_a"), TaskFlags { priority: Priority::default(), tags: HashSet::new(), due: None, dependencies: HashSet::new(), command: None }, None);
+        let dependencies = HashSet::from([0]);
+        task_handler.add(String::from("task_b"), TaskFlags { priority: Priority::default(), tags: HashSet::new(), due: None, dependencies: dependencies, command: None }, None);
+        let query = ListQuery { actionable_only: true, ..ListQuery::default() };
+        let task_b = task_handler.tasks.get(&1).unwrap();
+        assert!(!task_handler.matches_query(task_b, &query));
+        task_handler.mark_done(0, None);
+        let task_b = task_handler.tasks.get(&1).unwrap();
+        assert!(task_handler.matches_query(task_b, &query));
+    }
+    #[test]
+    fn set_parent_links_child_to_parent() {
+        let mut task_handler = TaskHandler::default();
+        task_handler.add(String::from("parent"), TaskFlags { priority: Priority::default(), tags: HashSet::new(), due: None, dependencies: HashSet::new(), command: None }, None);
+        task_handler.add(String::from("child"), TaskFlags { priority: Priority::default(), tags: HashSet::new(), due: None, dependencies: HashSet::new(), command: None }, None);
+        task_handler.set_parent(1, Some(0));
+        assert_eq!(Some(0), task_handler.tasks.get(&1).unwrap().parent);
+    }
+    #[test]
+    fn set_parent_rejects_cycle() {
+        let mut task_handler = TaskHandler::default();
+        task_handler.add(String::from("a"), TaskFlags { priority: Priority::default(), tags: HashSet::new(), due: None, dependencies: HashSet::new(), command: None }, None);
+        task_handler.add(String::from("b"), TaskFlags { priority: Priority::default(), tags: HashSet::new(), due: None, dependencies: HashSet::new(), command: None }, None);
+        task_handler.set_parent(1, Some(0));
+        task_handler.set_parent(0, Some(1));
+        assert_eq!(None, task_handler.tasks.get(&0).unwrap().parent);
+    }
+    #[test]
+    fn effective_status_requires_all_descendants_done() {
+        let mut task_handler = TaskHandler::default();
+        task_handler.add(String::from("parent"), TaskFlags { priority: Priority::default(), tags: HashSet::new(), due: None, dependencies: HashSet::new(), command: None }, None);
+        task_handler.add(String::from("child"), TaskFlags { priority: Priority::default(), tags: HashSet::new(), due: None, dependencies: HashSet::new(), command: None }, None);
+        task_handler.set_parent(1, Some(0));
+        task_handler.mark_done(0, None);
+        let parent = task_handler.tasks.get(&0).unwrap();
+        assert_eq!(Status::InProgress, task_handler.effective_status(parent));
+        task_handler.mark_done(1, None);
+        let parent = task_handler.tasks.get(&0).unwrap();
+        assert_eq!(Status::Done, task_handler.effective_status(parent));
+    }
+    #[test]
+    fn add_task_with_backdate_sets_created_at() {
+        let mut task_handler = TaskHandler::default();
+        let at = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000);
+        task_handler.add(
+            String::from("task_a"),
+            TaskFlags {
+                priority: Priority::default(),
+                tags: HashSet::new(),
+                due: None,
+                dependencies: HashSet::new(),
+                command: None,
+            },
+            Some(at),
+        );
+        let task = task_handler.tasks.get(&0).unwrap();
+        assert_eq!(at, task.created_at);
+        assert_eq!(at, task.updated_at);
+    }
+    #[test]
+    fn parse_backdate_resolves_relative_offset() {
+        let resolved = parse_backdate("-3600").unwrap();
+        let now = SystemTime::now();
+        assert!(resolved <= now - std::time::Duration::from_secs(3599));
+        assert!(resolved >= now - std::time::Duration::from_secs(3601));
+    }
+    #[test]
+    fn parse_backdate_rejects_non_numeric_token() {
+        assert!(parse_backdate("not-a-timestamp").is_err());
+    }
+    #[test]
+    fn run_marks_task_done_on_success() {
+        let mut task_handler = TaskHandler::default();
+        task_handler.add(
+            String::from("task_a"),
+            TaskFlags {
+                priority: Priority::default(),
+                tags: HashSet::new(),
+                due: None,
+                dependencies: HashSet::new(),
+                command: Some(String::from("exit 0")),
+            },
+            None,
+        );
+        task_handler.run(0);
+        let task = task_handler.tasks.get(&0).unwrap();
+        assert_eq!(Status::Done, task.status);
+        assert_eq!(1, task.run_history.len());
+        assert_eq!(0, task.run_history[0].return_code);
+    }
+    #[test]
+    fn run_keeps_task_in_progress_and_records_stderr_on_failure() {
+        let mut task_handler = TaskHandler::default();
+        task_handler.add(
+            String::from("task_a"),
+            TaskFlags {
+                priority: Priority::default(),
+                tags: HashSet::new(),
+                due: None,
+                dependencies: HashSet::new(),
+                command: Some(String::from("echo failed 1>&2; exit 1")),
+            },
+            None,
+        );
+        task_handler.run(0);
+        let task = task_handler.tasks.get(&0).unwrap();
+        assert_eq!(Status::InProgress, task.status);
+        assert_eq!(1, task.run_history.len());
+        assert_eq!(1, task.run_history[0].return_code);
+        assert_eq!("failed", task.run_history[0].stderr.trim());
+    }
+    #[test]
+    fn parse_tasks_round_trips_description_with_special_characters() {
+        let mut task_handler = TaskHandler::default();
+        task_handler.add(
+            String::from("desc with, comma: colon \"quotes\" and \\backslash"),
+            TaskFlags {
+                priority: Priority::default(),
+                tags: HashSet::new(),
+                due: None,
+                dependencies: HashSet::new(),
+                command: None,
+            },
+            None,
+        );
+        let data = task_handler.to_string();
+        let reparsed = TaskHandler::parse_tasks(&format!("{{ \"tasks\": {{ {} }} }}", data)).unwrap();
+        assert_eq!(
+            "desc with, comma: colon \"quotes\" and \\backslash",
+            reparsed.get(&0).unwrap().description
+        );
+    }
+    #[test]
+    fn parse_tasks_rejects_corrupt_file_without_panicking() {
+        let result = TaskHandler::parse_tasks("not valid json at all");
+        assert!(result.is_err());
+    }
+    #[test]
+    fn default_query_round_trips_through_save_encoding() {
+        let mut query = ListQuery::default();
+        query.status.insert(Status::Done);
+        query.priority = Some(Priority::High);
+        query.tag = Some(String::from("work"));
+        query.order = OrderKey::Due;
+        query.direction = SortDirection::Desc;
+        query.actionable_only = true;
+
+        let encoded = escape_json_string(&encode_list_query(&query));
+        let data = format!("{{ \"tasks\": {{ }}, \"default_query\": \"{}\" }}", encoded);
+        let decoded = TaskHandler::parse_default_query(&data);
+
+        assert_eq!(decoded.status, query.status);
+        assert_eq!(decoded.priority, query.priority);
+        assert_eq!(decoded.tag, query.tag);
+        assert_eq!(decoded.order, query.order);
+        assert_eq!(decoded.direction, query.direction);
+        assert_eq!(decoded.actionable_only, query.actionable_only);
+    }
+    #[test]
+    fn parse_default_query_falls_back_to_default_when_absent() {
+        let decoded = TaskHandler::parse_default_query("{ \"tasks\": {} }");
+        assert_eq!(decoded.order, OrderKey::Id);
+        assert!(!decoded.actionable_only);
+    }
+    #[test]
+    fn parse_tasks_round_trips_time_entry_message_with_quotes() {
+        let mut task_handler = TaskHandler::default();
+        task_handler.add(
+            String::from("task_a"),
+            TaskFlags {
+                priority: Priority::default(),
+                tags: HashSet::new(),
+                due: None,
+                dependencies: HashSet::new(),
+                command: None,
+            },
+            None,
+        );
+        task_handler.track(0, 1, 30, Some(String::from("msg with \"quote\" and \\slash")));
+        let data = task_handler.to_string();
+        let reparsed = TaskHandler::parse_tasks(&format!("{{ \"tasks\": {{ {} }} }}", data)).unwrap();
+        assert_eq!(
+            Some(String::from("msg with \"quote\" and \\slash")),
+            reparsed.get(&0).unwrap().time_entries[0].message
+        );
+    }
+    #[test]
+    fn parse_tasks_round_trips_run_output_with_quotes() {
+        let mut task_handler = TaskHandler::default();
+        task_handler.add(
+            String::from("task_a"),
+            TaskFlags {
+                priority: Priority::default(),
+                tags: HashSet::new(),
+                due: None,
+                dependencies: HashSet::new(),
+                command: Some(String::from("echo 'say \"hi\"' 1>&2; echo \"say \\\"bye\\\"\"")),
+            },
+            None,
+        );
+        task_handler.run(0);
+        let data = task_handler.to_string();
+        let reparsed = TaskHandler::parse_tasks(&format!("{{ \"tasks\": {{ {} }} }}", data)).unwrap();
+        let run = &reparsed.get(&0).unwrap().run_history[0];
+        assert_eq!("say \"hi\"", run.stderr.trim());
+        assert_eq!("say \"bye\"", run.stdout.trim());
+    }
 }